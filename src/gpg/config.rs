@@ -1,7 +1,75 @@
 use crate::args::Args;
 use anyhow::{anyhow, Result};
-use gpgme::{Gpgme, Protocol};
+use gpgme::{Context, Gpgme, LocateKey, Protocol};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::os::unix::io::FromRawFd;
 use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+/// Source to read the passphrase from when running non-interactively.
+///
+/// Used to drive gpgme's passphrase callback without a pinentry
+/// program, e.g. over SSH or from a script.
+#[derive(Clone, Debug)]
+pub enum PassphraseSource {
+	/// Read the passphrase from the standard input.
+	Stdin,
+	/// Read the passphrase from the given file descriptor.
+	Fd(i32),
+	/// Read the passphrase from the given file.
+	File(PathBuf),
+	/// Run the given shell command and use its output as the passphrase.
+	Command(String),
+}
+
+impl FromStr for PassphraseSource {
+	type Err = String;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (kind, value) = s.split_once(':').unwrap_or((s, ""));
+		match kind.to_lowercase().as_str() {
+			"stdin" => Ok(Self::Stdin),
+			"fd" => value
+				.parse()
+				.map(Self::Fd)
+				.map_err(|_| format!("invalid file descriptor: {}", value)),
+			"file" => Ok(Self::File(PathBuf::from(value))),
+			"cmd" => Ok(Self::Command(value.to_string())),
+			_ => Err(format!("unknown passphrase source: {}", s)),
+		}
+	}
+}
+
+impl PassphraseSource {
+	/// Reads the passphrase from the configured source.
+	pub fn read(&self) -> Result<String> {
+		let passphrase = match self {
+			Self::Stdin => {
+				let mut passphrase = String::new();
+				io::stdin().read_line(&mut passphrase)?;
+				passphrase
+			}
+			Self::Fd(fd) => {
+				// SAFETY: the fd is expected to be passed down by the
+				// caller (e.g. a shell redirection) and is not used
+				// anywhere else in the process.
+				let mut file = unsafe { fs::File::from_raw_fd(*fd) };
+				let mut passphrase = String::new();
+				file.read_to_string(&mut passphrase)?;
+				passphrase
+			}
+			Self::File(path) => fs::read_to_string(path)?,
+			Self::Command(command) => {
+				let output =
+					Command::new("sh").arg("-c").arg(command).output()?;
+				String::from_utf8(output.stdout)?
+			}
+		};
+		Ok(passphrase.trim_end_matches('\n').to_string())
+	}
+}
 
 /// Configuration manager for GPGME.
 #[derive(Clone, Debug)]
@@ -18,6 +86,15 @@ pub struct GpgConfig {
 	pub output_file: String,
 	/// Output directory.
 	pub output_dir: PathBuf,
+	/// Source to read the passphrase from, if running non-interactively.
+	pub passphrase_source: Option<PassphraseSource>,
+	/// Keyserver to use for receiving and locating keys.
+	pub keyserver: Option<String>,
+	/// Chain of sources to try in order when locating a key remotely.
+	pub locate_key_chain: LocateKey,
+	/// Raw gpgme context flags (e.g. `no-symkey-cache`, `trust-model`),
+	/// set via `gpgme_set_ctx_flag`.
+	pub flags: HashMap<String, String>,
 }
 
 impl GpgConfig {
@@ -36,6 +113,12 @@ impl GpgConfig {
 		if let Some(output) = &args.outdir {
 			output_dir = PathBuf::from(output);
 		}
+		let passphrase_source = args
+			.passphrase_from
+			.as_ref()
+			.map(|v| PassphraseSource::from_str(v))
+			.transpose()
+			.map_err(|e| anyhow!("invalid --passphrase-from value: {}", e))?;
 		Ok(Self {
 			inner: gpgme,
 			armor: args.armor,
@@ -43,11 +126,32 @@ impl GpgConfig {
 			home_dir,
 			output_file: args.outfile.to_string(),
 			output_dir,
+			passphrase_source,
+			keyserver: args.keyserver.as_ref().cloned(),
+			locate_key_chain: if args.keyserver.is_some() {
+				LocateKey::LOCAL
+					| LocateKey::KEYSERVER
+					| LocateKey::WKD
+					| LocateKey::DANE
+			} else {
+				LocateKey::LOCAL
+			},
+			flags: args
+				.ctx_flag
+				.iter()
+				.filter_map(|entry| entry.split_once('='))
+				.map(|(name, value)| (name.to_string(), value.to_string()))
+				.collect(),
 		})
 	}
 
 	/// Returns general information about the library configuration.
-	pub fn get_info(&mut self) -> Result<String> {
+	///
+	/// `context` is used to read back the active per-context flags
+	/// (see [`flags`]) via `gpgme_get_ctx_flag`.
+	///
+	/// [`flags`]: GpgConfig::flags
+	pub fn get_info(&mut self, context: &Context) -> Result<String> {
 		let engine_info = self.inner.engine_info()?;
 		let engine_info = engine_info.get(gpgme::Protocol::OpenPgp);
 		match engine_info {
@@ -62,6 +166,7 @@ impl GpgConfig {
 				Output directory: {:?}
 				Default signing key: {}
 				Armored output: {}
+				Context flags: {}
 				"#,
 				self.inner.version(),
 				engine.protocol(),
@@ -76,6 +181,15 @@ impl GpgConfig {
 					.cloned()
 					.unwrap_or_else(|| String::from("not specified")),
 				self.armor,
+				self.flags
+					.keys()
+					.map(|name| format!(
+						"{}={}",
+						name,
+						context.get_flag(name).unwrap_or("?")
+					))
+					.collect::<Vec<String>>()
+					.join(", "),
 			)),
 			None => Err(anyhow!("failed to get engine information")),
 		}
@@ -1,17 +1,92 @@
 use crate::gpg::config::GpgConfig;
-use crate::gpg::key::{GpgKey, KeyDetail, KeyType};
+use crate::gpg::key::{GpgKey, KeyDetail, KeyDetailLevel, KeyOrigin, KeyType};
 use anyhow::{anyhow, Result};
 use gpgme::context::Keys;
 use gpgme::{
-	Context, Data, ExportMode, Key, KeyListMode, PinentryMode, Protocol,
+	Context, Data, EditInteractionStatus, Editor, ExportMode, Key,
+	KeyListMode, LocateKey, PassphraseRequest, PinentryMode, Protocol,
+	SignKeyFlags, SignMode,
 };
 use serde::Serialize;
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::PathBuf;
+use std::process::Command as ProcessCommand;
+use std::time::SystemTime;
 use tinytemplate::TinyTemplate;
 
+/// Status of a single signature found while verifying data.
+#[derive(Clone, Debug)]
+pub struct SignatureStatus {
+	/// Fingerprint of the signer's key.
+	pub fingerprint: String,
+	/// Whether the signature is valid.
+	pub valid: bool,
+}
+
+/// Metadata produced while decrypting a message.
+///
+/// Mirrors the recipient/signature information mail clients show
+/// alongside a decrypted message.
+#[derive(Clone, Debug, Default)]
+pub struct DecryptionMetadata {
+	/// Key IDs the message was encrypted to.
+	///
+	/// Only the ID is kept, not gpgme's full per-recipient status
+	/// (e.g. whether that recipient's secret key was used).
+	pub recipients: Vec<String>,
+	/// Signatures found while verifying the decrypted data.
+	pub signatures: Vec<SignatureStatus>,
+}
+
+/// Step of the interactive `edit-key` state machine used to change
+/// the owner trust level of a key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TrustEditorStep {
+	/// Enter the `trust` command.
+	Command,
+	/// Waiting for `save`/`quit`.
+	Quit,
+}
+
+/// Drives gpg's interactive `edit-key` prompts to set the owner
+/// trust level of a key (`trust`, enter level, confirm, `save`).
+///
+/// Driven by the prompt keyword gpg reports in `args` rather than a
+/// fixed step order, since setting ultimate trust inserts an extra
+/// yes/no confirmation prompt that a fixed sequence would miss.
+struct TrustEditor {
+	/// Trust level to set, from 1 (unknown) to 5 (ultimate).
+	level: u8,
+	/// Current step of the state machine.
+	step: TrustEditorStep,
+}
+
+impl Editor for TrustEditor {
+	fn next(
+		&mut self,
+		_status: EditInteractionStatus,
+		args: Option<&str>,
+		out: &mut dyn Write,
+	) -> gpgme::Result<()> {
+		let response = match args.unwrap_or_default() {
+			"edit_ownertrust.value" => self.level.to_string(),
+			"edit_ownertrust.set_ultimate.okay" | "keyedit.save.okay" => {
+				String::from("y")
+			}
+			"keyedit.prompt" if self.step == TrustEditorStep::Command => {
+				self.step = TrustEditorStep::Quit;
+				String::from("trust")
+			}
+			"keyedit.prompt" => String::from("save"),
+			_ => String::new(),
+		};
+		writeln!(out, "{}", response)?;
+		Ok(())
+	}
+}
+
 /// Context to use for rendering the output template.
 #[derive(Serialize)]
 struct ExportContext<'a> {
@@ -34,15 +109,39 @@ pub struct GpgContext {
 }
 
 impl GpgContext {
+	/// Returns the default key-list mode used outside of a remote
+	/// lookup (see [`locate_keys`]).
+	///
+	/// [`locate_keys`]: GpgContext::locate_keys
+	fn default_key_list_mode() -> KeyListMode {
+		KeyListMode::LOCAL | KeyListMode::SIGS | KeyListMode::SIG_NOTATIONS
+	}
+
 	/// Constructs a new instance of `GpgContext`.
 	pub fn new(config: GpgConfig) -> Result<Self> {
 		let mut context = Context::from_protocol(Protocol::OpenPgp)?;
-		context.set_key_list_mode(
-			KeyListMode::LOCAL | KeyListMode::SIGS | KeyListMode::SIG_NOTATIONS,
-		)?;
+		context.set_key_list_mode(Self::default_key_list_mode())?;
 		context.set_armor(config.armor);
 		context.set_offline(false);
-		context.set_pinentry_mode(PinentryMode::Ask)?;
+		if let Some(passphrase_source) = &config.passphrase_source {
+			// Read once and reuse for every request: sources like a
+			// file descriptor or stdin can only be consumed once per
+			// process, and a static source has no "different" value
+			// to offer on a retry anyway. This means the uid hint and
+			// prev-was-bad flag on `PassphraseRequest` are ignored: a
+			// wrong passphrase is simply replayed identically on
+			// every subsequent attempt rather than being re-prompted.
+			let passphrase = passphrase_source.read()?;
+			context.set_pinentry_mode(PinentryMode::Loopback)?;
+			context.set_passphrase_provider(
+				move |_request: PassphraseRequest<'_>, out: &mut dyn Write| {
+					out.write_all(passphrase.as_bytes())?;
+					Ok(())
+				},
+			);
+		} else {
+			context.set_pinentry_mode(PinentryMode::Ask)?;
+		}
 		Ok(Self {
 			inner: context,
 			config,
@@ -50,8 +149,22 @@ impl GpgContext {
 	}
 
 	/// Applies the current configuration values to the context.
-	pub fn apply_config(&mut self) {
+	///
+	/// Also pushes every configured [`flag`] to the underlying gpgme
+	/// context via `gpgme_set_ctx_flag`.
+	///
+	/// [`flag`]: GpgConfig::flags
+	pub fn apply_config(&mut self) -> Result<()> {
 		self.inner.set_armor(self.config.armor);
+		for (flag, value) in &self.config.flags {
+			self.inner.set_flag(flag, value)?;
+		}
+		Ok(())
+	}
+
+	/// Returns general information about the library configuration.
+	pub fn get_info(&mut self) -> Result<String> {
+		self.config.get_info(&self.inner)
 	}
 
 	/// Returns the configured file path.
@@ -214,6 +327,88 @@ impl GpgContext {
 		Ok(path.to_string_lossy().to_string())
 	}
 
+	/// Points dirmngr at the configured keyserver.
+	///
+	/// gpgme has no per-context flag for the keyserver used by key
+	/// receive/locate operations (`gpgme_set_ctx_flag` only covers
+	/// things like `auto-key-locate` or `trust-model`) — the
+	/// keyserver is dirmngr's own engine configuration, so it is
+	/// updated there and dirmngr is asked to reload it.
+	fn configure_keyserver(&self) -> Result<()> {
+		let Some(keyserver) = &self.config.keyserver else {
+			return Ok(());
+		};
+		let config_path = self.config.home_dir.join("dirmngr.conf");
+		let mut lines: Vec<String> = if config_path.exists() {
+			fs::read_to_string(&config_path)?
+				.lines()
+				.filter(|line| !line.trim_start().starts_with("keyserver"))
+				.map(String::from)
+				.collect()
+		} else {
+			Vec::new()
+		};
+		lines.push(format!("keyserver {}", keyserver));
+		fs::write(&config_path, lines.join("\n") + "\n")?;
+		ProcessCommand::new("gpgconf")
+			.args(["--reload", "dirmngr"])
+			.output()?;
+		Ok(())
+	}
+
+	/// Receives the keys matching the given patterns from the
+	/// configured keyserver.
+	pub fn receive_keys(&mut self, patterns: Vec<String>) -> Result<u32> {
+		self.configure_keyserver()?;
+		Ok(self.inner.receive_keys(patterns)?.imported())
+	}
+
+	/// Searches for keys matching the given patterns.
+	///
+	/// Falls back to the configured remote sources (keyserver, WKD,
+	/// DANE, ...) if no local key matches, so a search transparently
+	/// extends to the network. The [`origin`] of each returned key
+	/// reflects where it was found.
+	///
+	/// [`origin`]: GpgKey::origin
+	pub fn locate_keys(
+		&mut self,
+		patterns: Vec<String>,
+	) -> Result<Vec<GpgKey>> {
+		let local_keys = self.get_keys(
+			KeyType::Public,
+			Some(patterns.clone()),
+			KeyDetailLevel::Minimum,
+		)?;
+		if !local_keys.is_empty() {
+			return Ok(local_keys);
+		}
+		self.configure_keyserver()?;
+		self.inner.set_auto_key_locate(self.config.locate_key_chain)?;
+		// `find_keys` only consults remote sources when the key-list
+		// mode includes `LOCATE`/`LOCATE_EXTERNAL` — auto-key-locate
+		// alone has no effect on a plain keylist.
+		self.inner.set_key_list_mode(
+			Self::default_key_list_mode()
+				| KeyListMode::LOCATE
+				| KeyListMode::LOCATE_EXTERNAL,
+		)?;
+		// Restore the context's mode before returning on *either*
+		// path: leaving it stuck in the remote-locate configuration
+		// after a lookup failure would silently affect every later
+		// key operation on this context.
+		let remote_keys = self
+			.get_keys_iter(KeyType::Public, Some(patterns))
+			.map(|keys| {
+				keys.filter_map(|key| key.ok())
+					.map(|key| GpgKey::from(key).with_origin(KeyOrigin::Remote))
+					.collect::<Vec<GpgKey>>()
+			});
+		self.inner.set_key_list_mode(Self::default_key_list_mode())?;
+		self.inner.set_auto_key_locate(LocateKey::LOCAL)?;
+		Ok(remote_keys?)
+	}
+
 	/// Sends the given key to the default keyserver.
 	pub fn send_key(&mut self, key_id: String) -> Result<String> {
 		let keys = self
@@ -230,6 +425,188 @@ impl GpgContext {
 		}
 	}
 
+	/// Encrypts the given input for the specified recipients.
+	///
+	/// Signs the input before encrypting it if `sign` is set to `true`, using
+	/// the configured [`default_key`]. Honors the [`armor`] flag for
+	/// the output encoding.
+	///
+	/// [`default_key`]: GpgConfig::default_key
+	/// [`armor`]: GpgConfig::armor
+	pub fn encrypt(
+		&mut self,
+		recipients: Vec<Key>,
+		input: Vec<u8>,
+		sign: bool,
+	) -> Result<Vec<u8>> {
+		let mut output = Vec::new();
+		if sign {
+			self.inner.clear_signers();
+			if let Some(default_key) = self.config.default_key.clone() {
+				let key = self.get_key(KeyType::Secret, default_key)?;
+				self.inner.add_signer(&key)?;
+			}
+			self.inner
+				.sign_and_encrypt(&recipients, input, &mut output)?;
+		} else {
+			self.inner.encrypt(&recipients, input, &mut output)?;
+		}
+		Ok(output)
+	}
+
+	/// Decrypts the given input.
+	///
+	/// Returns the decrypted data along with [`DecryptionMetadata`]
+	/// describing who the message was encrypted to and the status of
+	/// any signatures found while verifying it.
+	pub fn decrypt(
+		&mut self,
+		input: Vec<u8>,
+	) -> Result<(Vec<u8>, DecryptionMetadata)> {
+		let mut output = Vec::new();
+		let (decrypt_result, verify_result) =
+			self.inner.decrypt_and_verify(input, &mut output)?;
+		let metadata = DecryptionMetadata {
+			recipients: decrypt_result
+				.recipients()
+				.filter_map(|r| r.key_id().ok().map(String::from))
+				.collect(),
+			signatures: verify_result
+				.signatures()
+				.map(|signature| SignatureStatus {
+					fingerprint: signature
+						.fingerprint()
+						.unwrap_or("[?]")
+						.to_string(),
+					valid: signature.status().is_ok(),
+				})
+				.collect(),
+		};
+		Ok((output, metadata))
+	}
+
+	/// Signs the given input in the specified mode.
+	///
+	/// Uses the configured [`default_key`] as the signing key.
+	///
+	/// [`default_key`]: GpgConfig::default_key
+	pub fn sign(&mut self, input: Vec<u8>, mode: SignMode) -> Result<Vec<u8>> {
+		self.inner.clear_signers();
+		if let Some(default_key) = self.config.default_key.clone() {
+			let key = self.get_key(KeyType::Secret, default_key)?;
+			self.inner.add_signer(&key)?;
+		}
+		let mut output = Vec::new();
+		self.inner.sign(mode, input, &mut output)?;
+		Ok(output)
+	}
+
+	/// Verifies a detached signature against the given data.
+	///
+	/// Returns the status of each signature found.
+	pub fn verify(
+		&mut self,
+		signature: Vec<u8>,
+		signed_data: Vec<u8>,
+	) -> Result<Vec<SignatureStatus>> {
+		let result = self.inner.verify_detached(signature, signed_data)?;
+		Ok(result
+			.signatures()
+			.map(|signature| SignatureStatus {
+				fingerprint: signature
+					.fingerprint()
+					.unwrap_or("[?]")
+					.to_string(),
+				valid: signature.status().is_ok(),
+			})
+			.collect())
+	}
+
+	/// Certifies the target key using the signer's key.
+	///
+	/// Creates a non-exportable (local) certification when `local`
+	/// is set to `true`. Routes through the configured passphrase
+	/// path so the signer's key can be unlocked, and refreshes the
+	/// target key's [`KeyDetailLevel::Full`] signature view
+	/// afterward so the new certification appears immediately.
+	pub fn certify_key(
+		&mut self,
+		signer: Key,
+		target: Key,
+		uids: Vec<usize>,
+		local: bool,
+		expires: Option<SystemTime>,
+	) -> Result<GpgKey> {
+		self.inner.clear_signers();
+		self.inner.add_signer(&signer)?;
+		let mut flags = SignKeyFlags::empty();
+		if local {
+			flags |= SignKeyFlags::LOCAL;
+		}
+		// `sign_key_with_flags` takes the UID strings to certify, not
+		// their indices, so resolve each index against the target
+		// key's user IDs. An empty list signs all of them, so an
+		// out-of-range index must be rejected rather than dropped —
+		// silently falling back to "sign all" would over-certify.
+		let uids = uids
+			.into_iter()
+			.map(|i| {
+				target
+					.user_ids()
+					.nth(i)
+					.and_then(|u| u.id())
+					.map(String::from)
+					.ok_or_else(|| {
+						anyhow!("no user ID at index {} on target key", i)
+					})
+			})
+			.collect::<Result<Vec<String>>>()?;
+		self.inner
+			.sign_key_with_flags(&target, uids, expires, flags)?;
+		self.refresh_key(target)
+	}
+
+	/// Sets the owner trust level (1-5) of the given key.
+	///
+	/// Walks gpgme's interactive key-edit state machine to apply the
+	/// new trust level, routing through the configured passphrase
+	/// path, and refreshes the key's [`KeyDetailLevel::Full`]
+	/// signature view afterward.
+	pub fn set_owner_trust(&mut self, key: Key, level: u8) -> Result<GpgKey> {
+		if !(1..=5).contains(&level) {
+			return Err(anyhow!(
+				"trust level must be between 1 and 5, got {}",
+				level
+			));
+		}
+		let mut editor = TrustEditor {
+			level,
+			step: TrustEditorStep::Command,
+		};
+		let mut output = Vec::new();
+		self.inner.edit_key(&key, &mut editor, &mut output)?;
+		self.refresh_key(key)
+	}
+
+	/// Re-fetches the given key with full signature detail.
+	///
+	/// Used after an edit-key operation to reflect newly created
+	/// certifications or trust changes.
+	fn refresh_key(&mut self, key: Key) -> Result<GpgKey> {
+		let id = key
+			.id()
+			.ok_or_else(|| anyhow!("key has no ID"))?
+			.to_string();
+		self.get_keys(
+			KeyType::Public,
+			Some(vec![id]),
+			KeyDetailLevel::Full,
+		)?
+		.into_iter()
+		.next()
+		.ok_or_else(|| anyhow!("key not found after edit"))
+	}
+
 	/// Deletes the specified public/secret key.
 	///
 	/// Searches the keyring for finding the specified
@@ -278,7 +655,7 @@ mod tests {
 		let mut context = GpgContext::new(config)?;
 		assert_eq!(false, context.config.armor);
 		context.config.armor = true;
-		context.apply_config();
+		context.apply_config()?;
 		assert_eq!(true, context.config.armor);
 		let keys = context.get_all_keys(None)?;
 		let key_count = keys.get(&KeyType::Public).unwrap().len();
@@ -322,4 +699,48 @@ mod tests {
 		fs::remove_file(output_file)?;
 		Ok(())
 	}
+
+	/// Returns a context pointed at the populated test keyring.
+	fn test_context() -> Result<GpgContext> {
+		env::set_var(
+			"GNUPGHOME",
+			dirs_next::cache_dir()
+				.unwrap()
+				.join(env!("CARGO_PKG_NAME"))
+				.to_str()
+				.unwrap(),
+		);
+		let args = Args::default();
+		let config = GpgConfig::new(&args)?;
+		GpgContext::new(config)
+	}
+
+	#[test]
+	fn test_gpg_context_encrypt_decrypt() -> Result<()> {
+		let mut context = test_context()?;
+		let keys = context.get_all_keys(None)?;
+		let recipient = context.get_key(
+			KeyType::Public,
+			keys.get(&KeyType::Public).unwrap()[0].get_id(),
+		)?;
+		let plaintext = b"test message".to_vec();
+		let ciphertext =
+			context.encrypt(vec![recipient], plaintext.clone(), false)?;
+		let (decrypted, _metadata) = context.decrypt(ciphertext)?;
+		assert_eq!(plaintext, decrypted);
+		Ok(())
+	}
+
+	#[test]
+	fn test_gpg_context_sign_verify() -> Result<()> {
+		let mut context = test_context()?;
+		let keys = context.get_all_keys(None)?;
+		context.config.default_key =
+			Some(keys.get(&KeyType::Public).unwrap()[0].get_id());
+		let data = b"test data".to_vec();
+		let signature = context.sign(data.clone(), SignMode::Detach)?;
+		let signatures = context.verify(signature, data)?;
+		assert!(signatures.iter().any(|signature| signature.valid));
+		Ok(())
+	}
 }
@@ -24,6 +24,15 @@ impl Display for KeyType {
 	}
 }
 
+/// Origin of a key lookup result.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyOrigin {
+	/// Key was found in the local keyring.
+	Local,
+	/// Key was retrieved from a remote source (keyserver, WKD, DANE, ...).
+	Remote,
+}
+
 /// Level of detail to show for key.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum KeyDetailLevel {
@@ -53,6 +62,8 @@ pub struct GpgKey {
 	inner: Key,
 	/// Level of detail to show about key information.
 	pub detail: KeyDetailLevel,
+	/// Where this key was retrieved from.
+	pub origin: KeyOrigin,
 }
 
 impl From<Key> for GpgKey {
@@ -60,11 +71,18 @@ impl From<Key> for GpgKey {
 		Self {
 			inner: key,
 			detail: KeyDetailLevel::Minimum,
+			origin: KeyOrigin::Local,
 		}
 	}
 }
 
 impl GpgKey {
+	/// Sets the origin of the key.
+	pub fn with_origin(mut self, origin: KeyOrigin) -> Self {
+		self.origin = origin;
+		self
+	}
+
 	/// Returns the key ID with '0x' prefix.
 	pub fn get_id(&self) -> String {
 		self.inner